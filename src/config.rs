@@ -0,0 +1,46 @@
+//! Runtime configuration, parsed from CLI flags with environment-variable
+//! fallback (as pict-rs does with structopt), instead of the hardcoded
+//! bind address, paths, and board title this server started out with.
+
+use clap::Parser;
+
+const DEFAULT_MAX_FILE_BYTES: usize = 20 * 1024 * 1024;
+
+#[derive(Parser, Debug, Clone)]
+#[command(name = "imageboardz", about = "A minimal imageboard server")]
+pub struct Config {
+    /// Address the HTTP server binds to.
+    #[arg(long, env = "IMAGEBOARDZ_BIND", default_value = "0.0.0.0:8080")]
+    pub bind: String,
+
+    /// Path to the SQLite database file.
+    #[arg(long, env = "IMAGEBOARDZ_DB_PATH", default_value = "posts.db")]
+    pub db_path: String,
+
+    /// Root directory for uploaded media; `images/`, `videos/`, and
+    /// `thumbs/` subdirectories are created underneath it.
+    #[arg(long, env = "IMAGEBOARDZ_UPLOAD_DIR", default_value = "./uploads")]
+    pub upload_dir: String,
+
+    /// Maximum size, in bytes, accepted for a single uploaded file.
+    #[arg(long, env = "IMAGEBOARDZ_MAX_FILE_BYTES", default_value_t = DEFAULT_MAX_FILE_BYTES)]
+    pub max_file_bytes: usize,
+
+    /// Title shown in the page header and used as the board name.
+    #[arg(long, env = "IMAGEBOARDZ_BOARD_TITLE", default_value = "/a/ - Random")]
+    pub board_title: String,
+}
+
+impl Config {
+    pub fn image_dir(&self) -> String {
+        format!("{}/images/", self.upload_dir.trim_end_matches('/'))
+    }
+
+    pub fn video_dir(&self) -> String {
+        format!("{}/videos/", self.upload_dir.trim_end_matches('/'))
+    }
+
+    pub fn thumb_dir(&self) -> String {
+        format!("{}/thumbs/", self.upload_dir.trim_end_matches('/'))
+    }
+}
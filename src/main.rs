@@ -1,475 +1,697 @@
-use actix_files::Files;
-use actix_multipart::Multipart;
-use actix_web::{web, App, HttpResponse, HttpServer, Responder, middleware, Error};
-use chrono::Utc;
-use futures_util::stream::StreamExt;
-use html_escape::encode_safe;
-use log::{error, info};
-use mime_guess::mime;
-use rusqlite::{params, Connection};
-use serde::{Deserialize, Serialize};
-use std::fs as stdfs;
-use std::io::Write;
-use std::sync::{Arc, Mutex};
-use uuid::Uuid;
-
-#[derive(Serialize, Deserialize, Clone, Debug)]
-enum MediaType {
-    Image,
-    Video,
-}
-
-impl MediaType {
-    fn to_str(&self) -> &str {
-        match self {
-            MediaType::Image => "Image",
-            MediaType::Video => "Video",
-        }
-    }
-
-    fn from_str(s: &str) -> Option<MediaType> {
-        match s {
-            "Image" => Some(MediaType::Image),
-            "Video" => Some(MediaType::Video),
-            _ => None,
-        }
-    }
-}
-
-#[derive(Serialize, Deserialize, Clone)]
-struct Post {
-    id: i32,
-    name: String,
-    subject: String,
-    body: String,
-    timestamp: i64,
-    media_url: Option<String>,
-    media_type: Option<MediaType>,
-}
-
-const IMAGE_UPLOAD_DIR: &str = "./uploads/images/";
-const VIDEO_UPLOAD_DIR: &str = "./uploads/videos/";
-const DB_FILE: &str = "posts.db";
-
-#[actix_web::main]
-async fn main() -> std::io::Result<()> {
-    env_logger::init();
-
-    // Ensure directories
-    for dir in &[IMAGE_UPLOAD_DIR, VIDEO_UPLOAD_DIR] {
-        if !std::path::Path::new(dir).exists() {
-            stdfs::create_dir_all(dir)?;
-            info!("Created directory: {}", dir);
-        }
-    }
-
-    // Initialize SQLite database
-    let conn = Connection::open(DB_FILE).expect("Failed to open DB");
-    db_init(&conn).expect("Failed to initialize DB schema");
-
-    let conn = Arc::new(Mutex::new(conn));
-
-    HttpServer::new(move || {
-        App::new()
-            .app_data(web::Data::new(conn.clone()))
-            .wrap(middleware::Logger::default())
-            .service(Files::new("/static", "./static"))
-            .service(Files::new("/uploads/images", IMAGE_UPLOAD_DIR))
-            .service(Files::new("/uploads/videos", VIDEO_UPLOAD_DIR))
-            .route("/", web::get().to(homepage))
-            .route("/post", web::post().to(create_post))
-    })
-    .bind(("0.0.0.0", 8080))?
-    .run()
-    .await
-}
-
-fn db_init(conn: &Connection) -> rusqlite::Result<()> {
-    conn.execute(
-        "CREATE TABLE IF NOT EXISTS posts (
-            id INTEGER PRIMARY KEY AUTOINCREMENT,
-            name TEXT NOT NULL,
-            subject TEXT NOT NULL,
-            body TEXT NOT NULL,
-            timestamp INTEGER NOT NULL,
-            media_url TEXT,
-            media_type TEXT
-        )",
-        [],
-    )?;
-    Ok(())
-}
-
-fn load_posts_from_db(conn: &Connection) -> rusqlite::Result<Vec<Post>> {
-    let mut stmt = conn.prepare("SELECT id, name, subject, body, timestamp, media_url, media_type FROM posts ORDER BY timestamp DESC")?;
-    let rows = stmt.query_map([], |row| {
-        let media_type_str: Option<String> = row.get(6)?;
-        let media_type = media_type_str.as_deref().and_then(MediaType::from_str);
-
-        Ok(Post {
-            id: row.get(0)?,
-            name: row.get(1)?,
-            subject: row.get(2)?,
-            body: row.get(3)?,
-            timestamp: row.get(4)?,
-            media_url: row.get(5)?,
-            media_type,
-        })
-    })?;
-
-    let mut posts = Vec::new();
-    for row in rows {
-        posts.push(row?);
-    }
-    Ok(posts)
-}
-
-fn insert_post(conn: &Connection, post: &Post) -> rusqlite::Result<()> {
-    let media_type_str = post.media_type.as_ref().map(|m| m.to_str());
-    conn.execute(
-        "INSERT INTO posts (name, subject, body, timestamp, media_url, media_type)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-        params![
-            post.name,
-            post.subject,
-            post.body,
-            post.timestamp,
-            post.media_url,
-            media_type_str
-        ],
-    )?;
-    Ok(())
-}
-
-fn escape_html(input: &str) -> String {
-    encode_safe(input).to_string()
-}
-
-fn render_error_page(title: &str, message: &str) -> String {
-    format!(
-        r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-    <meta charset="UTF-8">
-    <title>{}</title>
-    <link rel="stylesheet" href="/static/css/style.css">
-</head>
-<body>
-    <h1>{}</h1>
-    <p>{}</p>
-    <a href="/">Back to Home</a>
-</body>
-</html>"#,
-        escape_html(title),
-        escape_html(title),
-        escape_html(message)
-    )
-}
-
-async fn homepage(conn_data: web::Data<Arc<Mutex<Connection>>>) -> impl Responder {
-    let posts = {
-        let conn = conn_data.lock().unwrap();
-        match load_posts_from_db(&conn) {
-            Ok(p) => p,
-            Err(e) => {
-                error!("Failed to load posts: {}", e);
-                return HttpResponse::InternalServerError()
-                    .content_type("text/html")
-                    .body(render_error_page("Internal Server Error", "Failed to load posts"));
-            }
-        }
-    };
-
-    let threads_html = if posts.is_empty() {
-        "<p>No posts yet.</p>".to_string()
-    } else {
-        posts.iter().map(render_post).collect::<Vec<_>>().join("\n")
-    };
-
-    let html = format!(
-r#"<!doctype html>
-<html lang="en">
-<head>
-<meta charset="utf-8">
-<title>/a/ - Random</title>
-<meta name="viewport" content="width=device-width, initial-scale=1, user-scalable=yes">
-<link rel="stylesheet" title="default" href="/static/css/style.css" type="text/css" media="screen">
-<link rel="stylesheet" title="style1" href="/static/css/1.css" type="text/css" media="screen" disabled="disabled">
-<link rel="stylesheet" title="style2" href="/static/css/2.css" type="text/css" media="screen" disabled="disabled">
-<link rel="stylesheet" title="style3" href="/static/css/3.css" type="text/css" media="screen" disabled="disabled">
-<link rel="stylesheet" title="style4" href="/static/css/4.css" type="text/css" media="screen" disabled="disabled">
-<link rel="stylesheet" title="style5" href="/static/css/5.css" type="text/css" media="screen" disabled="disabled">
-<link rel="stylesheet" title="style6" href="/static/css/6.css" type="text/css" media="screen" disabled="disabled">
-<link rel="stylesheet" title="style7" href="/static/css/7.css" type="text/css" media="screen" disabled="disabled">
-<link rel="stylesheet" href="/static/css/font-awesome/css/font-awesome.min.css">
-
-<script type="text/javascript">
-    const active_page = "index";
-    const board_name = "a";
-
-    function setActiveStyleSheet(title) {{
-        const links = document.getElementsByTagName("link");
-        for (let i = 0; i < links.length; i++) {{
-            const a = links[i];
-            if(a.getAttribute("rel") && a.getAttribute("rel").indexOf("stylesheet") !== -1 && a.getAttribute("title")) {{
-                a.disabled = true;
-                if(a.getAttribute("title") === title) a.disabled = false;
-            }}
-        }}
-        localStorage.setItem('selectedStyle', title);
-    }}
-
-    window.addEventListener('load', () => {{
-        const savedStyle = localStorage.getItem('selectedStyle');
-        if(savedStyle) {{
-            setActiveStyleSheet(savedStyle);
-        }}
-    }});
-</script>
-
-<script type="text/javascript" src="/static/js/jquery.min.js"></script>
-<script type="text/javascript" src="/static/js/main.js"></script>
-<script type="text/javascript" src="/static/js/inline-expanding.js"></script>
-<script type="text/javascript" src="/static/js/hide-form.js"></script>
-</head>
-<body class="visitor is-not-moderator active-index" data-stylesheet="default">
-<header><h1>/a/ - Random</h1><div class="subtitle"></div></header>
-<form name="post" enctype="multipart/form-data" action="/post" method="post">
-<input type="hidden" name="csrf_token" value="TODO_CSRF_TOKEN">
-<table>
-    <tr><th>Name</th><td><input type="text" name="name" size="25" maxlength="35" required></td></tr>
-    <tr><th>Subject</th><td><input type="text" name="subject" size="25" maxlength="100" required>
-        <input type="submit" name="post" value="New Topic" style="margin-left:2px;"></td></tr>
-    <tr><th>Comment</th><td><textarea name="body" id="body" rows="5" cols="35" required></textarea></td></tr>
-    <tr id="upload"><th>File</th><td><input type="file" name="file" accept=".jpg,.jpeg,.png,.gif,.webp,.mp4"></td></tr>
-</table>
-</form>
-<hr />
-{threads}
-<div class="pagination"><strong>1</strong> </div><footer>
-    <!-- Style selector -->
-    <div id="style-selector">
-        <label for="style_select">Style:</label>
-        <select id="style_select" onchange="setActiveStyleSheet(this.value)">
-            <option value="default">default</option>
-            <option value="style1">style1</option>
-            <option value="style2">style2</option>
-            <option value="style3">style3</option>
-            <option value="style4">style4</option>
-            <option value="style5">style5</option>
-            <option value="style6">style6</option>
-            <option value="style7">style7</option>
-        </select>
-    </div>
-
-    <p class="unimportant">
-        All trademarks, copyrights,
-        comments, and images on this page are owned by and are
-        the responsibility of their respective parties.
-    </p>
-
-    <div style="text-align:center; margin-top:10px;">
-        <a href="https://example.com/">COM</a> | 
-        <a href="https://example.net/">NET</a> |
-        <a href="https://example.org/">ORG</a>
-    </div>
-</footer>
-
-<div id="home-button">
-    <a href="../">Home</a>
-</div>
-
-<script type="text/javascript">ready();</script>
-</body>
-</html>"#,
-threads = threads_html
-    );
-
-    HttpResponse::Ok().content_type("text/html").body(html)
-}
-
-fn render_post(post: &Post) -> String {
-    let files_html = if let Some(url) = &post.media_url {
-        match post.media_type {
-            Some(MediaType::Image) => format!(
-                r#"<div class="files">
-    <div class="file">
-        <p class="fileinfo">File: <a href="{}">{}</a></p>
-        <a href="{}" target="_blank"><img class="post-image" src="{}" alt="" /></a>
-    </div>
-</div>"#,
-                escape_html(url),
-                escape_html(url),
-                escape_html(url),
-                escape_html(url)
-            ),
-            Some(MediaType::Video) => format!(
-                r#"<div class="files">
-    <div class="file">
-        <p class="fileinfo">File: <a href="{}">{}</a></p>
-        <video class="post-video" controls>
-            <source src="{}" type="video/mp4">
-            Your browser does not support the video tag.
-        </video>
-    </div>
-</div>"#,
-                escape_html(url),
-                escape_html(url),
-                escape_html(url)
-            ),
-            None => "".to_string(),
-        }
-    } else {
-        "".to_string()
-    };
-
-    format!(
-        r#"<div class="thread" id="thread_{id}" data-board="a">
-{files}
-<div class="post op" id="op_{id}">
-<p class="intro"><span class="subject">{subject}</span> <span class="name">{name}</span>
-    &nbsp;<a href="threads/thread_{id}.html">Reply</a>
-</p>
-<div class="body">{body}</div>
-</div>
-<br class="clear"/>
-<hr/>
-</div>"#,
-        id = post.id,
-        files = files_html,
-        subject = escape_html(&post.subject),
-        name = escape_html(&post.name),
-        body = escape_html(&post.body)
-    )
-}
-
-async fn create_post(
-    conn_data: web::Data<Arc<Mutex<Connection>>>,
-    mut payload: Multipart,
-) -> Result<HttpResponse, Error> {
-    let mut name = String::new();
-    let mut subject = String::new();
-    let mut body = String::new();
-    let mut media_url: Option<String> = None;
-    let mut media_type: Option<MediaType> = None;
-
-    while let Some(item) = payload.next().await {
-        let mut field = item?;
-        let content_disposition = field.content_disposition();
-        let field_name = if let Some(n) = content_disposition.get_name() {
-            n
-        } else {
-            continue;
-        };
-
-        match field_name {
-            "name" => {
-                while let Some(chunk) = field.next().await {
-                    let data = chunk?;
-                    name.push_str(&String::from_utf8_lossy(&data));
-                }
-            }
-            "subject" => {
-                while let Some(chunk) = field.next().await {
-                    let data = chunk?;
-                    subject.push_str(&String::from_utf8_lossy(&data));
-                }
-            }
-            "body" => {
-                while let Some(chunk) = field.next().await {
-                    let data = chunk?;
-                    body.push_str(&String::from_utf8_lossy(&data));
-                }
-            }
-            "file" => {
-                if let Some(filename) = content_disposition.get_filename() {
-                    if filename.trim().is_empty() {
-                        continue;
-                    }
-                    let mime_type = mime_guess::from_path(&filename).first_or_octet_stream();
-                    match mime_type.type_() {
-                        mime::IMAGE => {
-                            if !matches!(mime_type.subtype().as_ref(), "jpeg" | "jpg" | "png" | "gif" | "webp") {
-                                return Ok(HttpResponse::BadRequest().body("Unsupported image format"));
-                            }
-
-                            let unique_id = Uuid::new_v4().to_string();
-                            let extension = mime_type.subtype().as_str();
-                            let sanitized_filename = format!("{}.{}", unique_id, extension);
-                            let filepath = format!("{}{}", IMAGE_UPLOAD_DIR, sanitized_filename);
-                            let filepath_clone = filepath.clone();
-
-                            let mut f = web::block(move || stdfs::File::create(&filepath_clone)).await??;
-                            while let Some(chunk) = field.next().await {
-                                let data = chunk?;
-                                f = web::block(move || f.write_all(&data).map(|_| f)).await??;
-                            }
-
-                            if image::open(&filepath).is_err() {
-                                stdfs::remove_file(&filepath)?;
-                                return Ok(HttpResponse::BadRequest().body("Invalid image file"));
-                            }
-
-                            media_url = Some(format!("/uploads/images/{}", sanitized_filename));
-                            media_type = Some(MediaType::Image);
-                        }
-                        mime::VIDEO => {
-                            if mime_type.subtype().as_ref() != "mp4" {
-                                return Ok(HttpResponse::BadRequest().body("Unsupported video format"));
-                            }
-
-                            let unique_id = Uuid::new_v4().to_string();
-                            let extension = mime_type.subtype().as_str();
-                            let sanitized_filename = format!("{}.{}", unique_id, extension);
-                            let filepath = format!("{}{}", VIDEO_UPLOAD_DIR, sanitized_filename);
-                            let filepath_clone = filepath.clone();
-
-                            let mut f = web::block(move || stdfs::File::create(&filepath_clone)).await??;
-                            while let Some(chunk) = field.next().await {
-                                let data = chunk?;
-                                f = web::block(move || f.write_all(&data).map(|_| f)).await??;
-                            }
-
-                            media_url = Some(format!("/uploads/videos/{}", sanitized_filename));
-                            media_type = Some(MediaType::Video);
-                        }
-                        _ => {
-                            return Ok(HttpResponse::BadRequest().body("Unsupported media type"));
-                        }
-                    }
-                }
-            }
-            _ => {}
-        }
-    }
-
-    if name.trim().is_empty() || subject.trim().is_empty() || body.trim().is_empty() {
-        return Ok(HttpResponse::BadRequest()
-            .content_type("text/html")
-            .body(render_error_page("Bad Request", "Name, Subject, and Comment cannot be empty")));
-    }
-
-    let post = Post {
-        id: 0, // Will be assigned by autoincrement
-        name: name.trim().to_string(),
-        subject: subject.trim().to_string(),
-        body: body.trim().to_string(),
-        timestamp: Utc::now().timestamp(),
-        media_url,
-        media_type,
-    };
-
-    {
-        let conn = conn_data.lock().unwrap();
-        if let Err(e) = insert_post(&conn, &post) {
-            error!("Failed to save post: {}", e);
-            return Ok(HttpResponse::InternalServerError()
-                .content_type("text/html")
-                .body(render_error_page("Internal Server Error", "Failed to save post")));
-        }
-    }
-
-    Ok(HttpResponse::SeeOther()
-        .append_header(("Location", "/"))
-        .finish())
-}
+use actix_files::Files;
+use actix_multipart::Multipart;
+use actix_web::{web, App, HttpResponse, HttpServer, Responder, middleware, Error};
+use chrono::Utc;
+use clap::Parser;
+use futures_util::stream::StreamExt;
+use html_escape::encode_safe;
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::fs as stdfs;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::{error, info, instrument};
+
+mod config;
+mod processor;
+mod range;
+mod upload_manager;
+mod validate;
+
+use config::Config;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+enum MediaType {
+    Image,
+    Video,
+}
+
+impl MediaType {
+    fn to_str(&self) -> &str {
+        match self {
+            MediaType::Image => "Image",
+            MediaType::Video => "Video",
+        }
+    }
+
+    fn from_str(s: &str) -> Option<MediaType> {
+        match s {
+            "Image" => Some(MediaType::Image),
+            "Video" => Some(MediaType::Video),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct Post {
+    id: i32,
+    name: String,
+    subject: String,
+    body: String,
+    timestamp: i64,
+    media_url: Option<String>,
+    media_type: Option<MediaType>,
+    thumb_url: Option<String>,
+    /// Cached from `upload_manager::Details` at upload time so `render_post`
+    /// can size the `<img>` tag without re-probing the file.
+    media_width: Option<u32>,
+    media_height: Option<u32>,
+}
+
+#[actix_web::main]
+async fn main() -> std::io::Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
+        .init();
+
+    let config = Config::parse();
+
+    // Ensure directories
+    for dir in &[config.image_dir(), config.video_dir(), config.thumb_dir()] {
+        if !std::path::Path::new(dir).exists() {
+            stdfs::create_dir_all(dir)?;
+            info!("Created directory: {}", dir);
+        }
+    }
+
+    // Initialize SQLite database
+    let conn = Connection::open(&config.db_path).expect("Failed to open DB");
+    db_init(&conn).expect("Failed to initialize DB schema");
+
+    let conn = Arc::new(Mutex::new(conn));
+    let bind = config.bind.clone();
+
+    HttpServer::new(move || {
+        App::new()
+            .app_data(web::Data::new(conn.clone()))
+            .app_data(web::Data::new(config.clone()))
+            .wrap(middleware::Logger::default())
+            .service(Files::new("/static", "./static"))
+            .service(Files::new("/uploads/thumbs", config.thumb_dir()))
+            .route("/", web::get().to(homepage))
+            .route("/post", web::post().to(create_post))
+            .route("/uploads/images/{filename}", web::get().to(range::serve_image))
+            .route("/uploads/videos/{filename}", web::get().to(range::serve_video))
+    })
+    .bind(&bind)?
+    .run()
+    .await
+}
+
+fn db_init(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS posts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            subject TEXT NOT NULL,
+            body TEXT NOT NULL,
+            timestamp INTEGER NOT NULL,
+            media_url TEXT,
+            media_type TEXT,
+            thumb_url TEXT,
+            media_width INTEGER,
+            media_height INTEGER
+        )",
+        [],
+    )?;
+    // Older databases were created before these columns existed; add them if missing.
+    let _ = conn.execute("ALTER TABLE posts ADD COLUMN thumb_url TEXT", []);
+    let _ = conn.execute("ALTER TABLE posts ADD COLUMN media_width INTEGER", []);
+    let _ = conn.execute("ALTER TABLE posts ADD COLUMN media_height INTEGER", []);
+    upload_manager::db_init(conn)?;
+    Ok(())
+}
+
+fn load_posts_from_db(conn: &Connection) -> rusqlite::Result<Vec<Post>> {
+    let mut stmt = conn.prepare("SELECT id, name, subject, body, timestamp, media_url, media_type, thumb_url, media_width, media_height FROM posts ORDER BY timestamp DESC")?;
+    let rows = stmt.query_map([], |row| {
+        let media_type_str: Option<String> = row.get(6)?;
+        let media_type = media_type_str.as_deref().and_then(MediaType::from_str);
+
+        Ok(Post {
+            id: row.get(0)?,
+            name: row.get(1)?,
+            subject: row.get(2)?,
+            body: row.get(3)?,
+            timestamp: row.get(4)?,
+            media_url: row.get(5)?,
+            media_type,
+            thumb_url: row.get(7)?,
+            media_width: row.get(8)?,
+            media_height: row.get(9)?,
+        })
+    })?;
+
+    let mut posts = Vec::new();
+    for row in rows {
+        posts.push(row?);
+    }
+    Ok(posts)
+}
+
+#[instrument(skip(conn, post), fields(subject = %post.subject, has_media = post.media_url.is_some()))]
+fn insert_post(conn: &Connection, post: &Post) -> rusqlite::Result<()> {
+    let media_type_str = post.media_type.as_ref().map(|m| m.to_str());
+    conn.execute(
+        "INSERT INTO posts (name, subject, body, timestamp, media_url, media_type, thumb_url, media_width, media_height)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![
+            post.name,
+            post.subject,
+            post.body,
+            post.timestamp,
+            post.media_url,
+            media_type_str,
+            post.thumb_url,
+            post.media_width,
+            post.media_height,
+        ],
+    )?;
+    Ok(())
+}
+
+fn escape_html(input: &str) -> String {
+    encode_safe(input).to_string()
+}
+
+fn render_error_page(title: &str, message: &str, board_title: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <title>{} - {}</title>
+    <link rel="stylesheet" href="/static/css/style.css">
+</head>
+<body>
+    <h1>{}</h1>
+    <p>{}</p>
+    <a href="/">Back to {}</a>
+</body>
+</html>"#,
+        escape_html(title),
+        escape_html(board_title),
+        escape_html(title),
+        escape_html(message),
+        escape_html(board_title)
+    )
+}
+
+async fn homepage(
+    conn_data: web::Data<Arc<Mutex<Connection>>>,
+    config: web::Data<Config>,
+) -> impl Responder {
+    let posts = {
+        let conn = conn_data.lock().unwrap();
+        match load_posts_from_db(&conn) {
+            Ok(p) => p,
+            Err(e) => {
+                error!("Failed to load posts: {}", e);
+                return HttpResponse::InternalServerError().content_type("text/html").body(
+                    render_error_page(
+                        "Internal Server Error",
+                        "Failed to load posts",
+                        &config.board_title,
+                    ),
+                );
+            }
+        }
+    };
+
+    let threads_html = if posts.is_empty() {
+        "<p>No posts yet.</p>".to_string()
+    } else {
+        posts.iter().map(render_post).collect::<Vec<_>>().join("\n")
+    };
+
+    let html = format!(
+r#"<!doctype html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>{board_title}</title>
+<meta name="viewport" content="width=device-width, initial-scale=1, user-scalable=yes">
+<link rel="stylesheet" title="default" href="/static/css/style.css" type="text/css" media="screen">
+<link rel="stylesheet" title="style1" href="/static/css/1.css" type="text/css" media="screen" disabled="disabled">
+<link rel="stylesheet" title="style2" href="/static/css/2.css" type="text/css" media="screen" disabled="disabled">
+<link rel="stylesheet" title="style3" href="/static/css/3.css" type="text/css" media="screen" disabled="disabled">
+<link rel="stylesheet" title="style4" href="/static/css/4.css" type="text/css" media="screen" disabled="disabled">
+<link rel="stylesheet" title="style5" href="/static/css/5.css" type="text/css" media="screen" disabled="disabled">
+<link rel="stylesheet" title="style6" href="/static/css/6.css" type="text/css" media="screen" disabled="disabled">
+<link rel="stylesheet" title="style7" href="/static/css/7.css" type="text/css" media="screen" disabled="disabled">
+<link rel="stylesheet" href="/static/css/font-awesome/css/font-awesome.min.css">
+
+<script type="text/javascript">
+    const active_page = "index";
+    const board_name = "a";
+
+    function setActiveStyleSheet(title) {{
+        const links = document.getElementsByTagName("link");
+        for (let i = 0; i < links.length; i++) {{
+            const a = links[i];
+            if(a.getAttribute("rel") && a.getAttribute("rel").indexOf("stylesheet") !== -1 && a.getAttribute("title")) {{
+                a.disabled = true;
+                if(a.getAttribute("title") === title) a.disabled = false;
+            }}
+        }}
+        localStorage.setItem('selectedStyle', title);
+    }}
+
+    window.addEventListener('load', () => {{
+        const savedStyle = localStorage.getItem('selectedStyle');
+        if(savedStyle) {{
+            setActiveStyleSheet(savedStyle);
+        }}
+    }});
+</script>
+
+<script type="text/javascript" src="/static/js/jquery.min.js"></script>
+<script type="text/javascript" src="/static/js/main.js"></script>
+<script type="text/javascript" src="/static/js/inline-expanding.js"></script>
+<script type="text/javascript" src="/static/js/hide-form.js"></script>
+</head>
+<body class="visitor is-not-moderator active-index" data-stylesheet="default">
+<header><h1>{board_title}</h1><div class="subtitle"></div></header>
+<form name="post" enctype="multipart/form-data" action="/post" method="post">
+<input type="hidden" name="csrf_token" value="TODO_CSRF_TOKEN">
+<table>
+    <tr><th>Name</th><td><input type="text" name="name" size="25" maxlength="35" required></td></tr>
+    <tr><th>Subject</th><td><input type="text" name="subject" size="25" maxlength="100" required>
+        <input type="submit" name="post" value="New Topic" style="margin-left:2px;"></td></tr>
+    <tr><th>Comment</th><td><textarea name="body" id="body" rows="5" cols="35" required></textarea></td></tr>
+    <tr id="upload"><th>File</th><td><input type="file" name="file" accept=".jpg,.jpeg,.png,.gif,.webp,.mp4"></td></tr>
+</table>
+</form>
+<hr />
+{threads}
+<div class="pagination"><strong>1</strong> </div><footer>
+    <!-- Style selector -->
+    <div id="style-selector">
+        <label for="style_select">Style:</label>
+        <select id="style_select" onchange="setActiveStyleSheet(this.value)">
+            <option value="default">default</option>
+            <option value="style1">style1</option>
+            <option value="style2">style2</option>
+            <option value="style3">style3</option>
+            <option value="style4">style4</option>
+            <option value="style5">style5</option>
+            <option value="style6">style6</option>
+            <option value="style7">style7</option>
+        </select>
+    </div>
+
+    <p class="unimportant">
+        All trademarks, copyrights,
+        comments, and images on this page are owned by and are
+        the responsibility of their respective parties.
+    </p>
+
+    <div style="text-align:center; margin-top:10px;">
+        <a href="https://example.com/">COM</a> | 
+        <a href="https://example.net/">NET</a> |
+        <a href="https://example.org/">ORG</a>
+    </div>
+</footer>
+
+<div id="home-button">
+    <a href="../">Home</a>
+</div>
+
+<script type="text/javascript">ready();</script>
+</body>
+</html>"#,
+board_title = escape_html(&config.board_title),
+threads = threads_html
+    );
+
+    HttpResponse::Ok().content_type("text/html").body(html)
+}
+
+fn render_post(post: &Post) -> String {
+    let files_html = if let Some(url) = &post.media_url {
+        match post.media_type {
+            Some(MediaType::Image) => {
+                let thumb = post.thumb_url.as_deref().unwrap_or(url);
+                let dims_attr = match (post.media_width, post.media_height) {
+                    (Some(w), Some(h)) if w > 0 && h > 0 => format!(r#" width="{}" height="{}""#, w, h),
+                    _ => String::new(),
+                };
+                format!(
+                    r#"<div class="files">
+    <div class="file">
+        <p class="fileinfo">File: <a href="{}">{}</a></p>
+        <a href="{}" target="_blank"><img class="post-image" src="{}" alt=""{dims}/></a>
+    </div>
+</div>"#,
+                    escape_html(url),
+                    escape_html(url),
+                    escape_html(url),
+                    escape_html(thumb),
+                    dims = dims_attr
+                )
+            }
+            Some(MediaType::Video) => {
+                let poster_attr = post
+                    .thumb_url
+                    .as_deref()
+                    .map(|t| format!(r#" poster="{}""#, escape_html(t)))
+                    .unwrap_or_default();
+                format!(
+                    r#"<div class="files">
+    <div class="file">
+        <p class="fileinfo">File: <a href="{}">{}</a></p>
+        <video class="post-video" controls{poster}>
+            <source src="{}" type="video/mp4">
+            Your browser does not support the video tag.
+        </video>
+    </div>
+</div>"#,
+                    escape_html(url),
+                    escape_html(url),
+                    escape_html(url),
+                    poster = poster_attr
+                )
+            }
+            None => "".to_string(),
+        }
+    } else {
+        "".to_string()
+    };
+
+    format!(
+        r#"<div class="thread" id="thread_{id}" data-board="a">
+{files}
+<div class="post op" id="op_{id}">
+<p class="intro"><span class="subject">{subject}</span> <span class="name">{name}</span>
+    &nbsp;<a href="threads/thread_{id}.html">Reply</a>
+</p>
+<div class="body">{body}</div>
+</div>
+<br class="clear"/>
+<hr/>
+</div>"#,
+        id = post.id,
+        files = files_html,
+        subject = escape_html(&post.subject),
+        name = escape_html(&post.name),
+        body = escape_html(&post.body)
+    )
+}
+
+#[instrument(
+    skip(conn_data, config, payload),
+    fields(upload_size, sniffed_mime, generated_filename, elapsed_ms)
+)]
+async fn create_post(
+    conn_data: web::Data<Arc<Mutex<Connection>>>,
+    config: web::Data<Config>,
+    mut payload: Multipart,
+) -> Result<HttpResponse, Error> {
+    let start = Instant::now();
+    let span = tracing::Span::current();
+
+    let mut name = String::new();
+    let mut subject = String::new();
+    let mut body = String::new();
+    let mut media_url: Option<String> = None;
+    let mut media_type: Option<MediaType> = None;
+    let mut thumb_url: Option<String> = None;
+    let mut media_width: Option<u32> = None;
+    let mut media_height: Option<u32> = None;
+
+    while let Some(item) = payload.next().await {
+        let mut field = item?;
+        let content_disposition = field.content_disposition();
+        let field_name = if let Some(n) = content_disposition.get_name() {
+            n
+        } else {
+            continue;
+        };
+
+        match field_name {
+            "name" => {
+                while let Some(chunk) = field.next().await {
+                    let data = chunk?;
+                    name.push_str(&String::from_utf8_lossy(&data));
+                }
+            }
+            "subject" => {
+                while let Some(chunk) = field.next().await {
+                    let data = chunk?;
+                    subject.push_str(&String::from_utf8_lossy(&data));
+                }
+            }
+            "body" => {
+                while let Some(chunk) = field.next().await {
+                    let data = chunk?;
+                    body.push_str(&String::from_utf8_lossy(&data));
+                }
+            }
+            "file" => {
+                if let Some(filename) = content_disposition.get_filename() {
+                    if filename.trim().is_empty() {
+                        continue;
+                    }
+
+                    let mut buf = Vec::new();
+                    while let Some(chunk) = field.next().await {
+                        let data = chunk?;
+                        if buf.len() + data.len() > config.max_file_bytes {
+                            return Ok(HttpResponse::PayloadTooLarge().body(format!(
+                                "File exceeds the {} byte limit",
+                                config.max_file_bytes
+                            )));
+                        }
+                        buf.extend_from_slice(&data);
+                    }
+                    span.record("upload_size", buf.len());
+
+                    let sniffed = match validate::sniff(&buf) {
+                        Some(fmt) => fmt,
+                        None => {
+                            return Ok(HttpResponse::BadRequest().body("Unsupported file format"));
+                        }
+                    };
+                    span.record("sniffed_mime", tracing::field::debug(sniffed));
+
+                    if sniffed.is_video() {
+                        let bytes = buf.clone();
+                        let video_dir = config.video_dir();
+                        let (hash, filepath) = web::block(move || {
+                            upload_manager::store(&video_dir, &bytes, sniffed.extension())
+                        })
+                        .await??;
+
+                        let already_known = {
+                            let conn = conn_data.lock().unwrap();
+                            match upload_manager::lookup(&conn, &hash) {
+                                Ok(details) => details.is_some(),
+                                Err(e) => {
+                                    error!("Failed to look up media by hash: {}", e);
+                                    return Ok(HttpResponse::InternalServerError()
+                                        .content_type("text/html")
+                                        .body(render_error_page(
+                                            "Internal Server Error",
+                                            "Failed to process upload",
+                                            &config.board_title,
+                                        )));
+                                }
+                            }
+                        };
+
+                        let thumb_filename = format!("{}.png", hash);
+                        let thumb_path = format!("{}{}", config.thumb_dir(), thumb_filename);
+                        if !std::path::Path::new(&thumb_path).exists() {
+                            let src_for_thumb = std::path::PathBuf::from(&filepath);
+                            let dest_for_thumb = std::path::PathBuf::from(&thumb_path);
+                            if let Err(e) = web::block(move || {
+                                processor::process_video(&src_for_thumb, &dest_for_thumb)
+                            })
+                            .await?
+                            {
+                                error!("Failed to generate video thumbnail for {} (is ffmpeg installed?): {}", filepath, e);
+                            }
+                        }
+                        // Only link a thumbnail that actually exists on disk: `ffmpeg`
+                        // is a required external dependency for video posters, and if
+                        // it's missing or fails, `render_post` must fall back to no
+                        // poster rather than a 404'ing `<video poster>`.
+                        if std::path::Path::new(&thumb_path).exists() {
+                            thumb_url = Some(format!("/uploads/thumbs/{}", thumb_filename));
+                        }
+
+                        if !already_known {
+                            let conn = conn_data.lock().unwrap();
+                            if let Err(e) = upload_manager::insert(
+                                &conn,
+                                &hash,
+                                &upload_manager::Details {
+                                    path: filepath.clone(),
+                                    mime: "video/mp4".to_string(),
+                                    width: 0,
+                                    height: 0,
+                                },
+                            ) {
+                                error!("Failed to record media details: {}", e);
+                                return Ok(HttpResponse::InternalServerError()
+                                    .content_type("text/html")
+                                    .body(render_error_page(
+                                        "Internal Server Error",
+                                        "Failed to process upload",
+                                        &config.board_title,
+                                    )));
+                            }
+                        }
+
+                        let generated_filename = format!("{}.{}", hash, sniffed.extension());
+                        span.record("generated_filename", &generated_filename);
+                        media_url = Some(format!("/uploads/videos/{}", generated_filename));
+                        media_type = Some(MediaType::Video);
+                    } else {
+                        // GIFs may be animated, so only still formats are re-encoded;
+                        // the original bytes are kept to preserve animation.
+                        let persisted = if sniffed == validate::SniffedFormat::Gif {
+                            if let Err(e) = validate::verify_gif(&buf) {
+                                return Ok(HttpResponse::BadRequest()
+                                    .body(format!("Invalid image file: {}", e)));
+                            }
+                            buf.clone()
+                        } else {
+                            match validate::reencode_image(&buf, sniffed) {
+                                Ok(bytes) => bytes,
+                                Err(e) => {
+                                    return Ok(HttpResponse::BadRequest()
+                                        .body(format!("Invalid image file: {}", e)));
+                                }
+                            }
+                        };
+
+                        let bytes = persisted.clone();
+                        let image_dir = config.image_dir();
+                        let (hash, filepath) = web::block(move || {
+                            upload_manager::store(&image_dir, &bytes, sniffed.extension())
+                        })
+                        .await??;
+
+                        let existing_details = {
+                            let conn = conn_data.lock().unwrap();
+                            match upload_manager::lookup(&conn, &hash) {
+                                Ok(details) => details,
+                                Err(e) => {
+                                    error!("Failed to look up media by hash: {}", e);
+                                    return Ok(HttpResponse::InternalServerError()
+                                        .content_type("text/html")
+                                        .body(render_error_page(
+                                            "Internal Server Error",
+                                            "Failed to process upload",
+                                            &config.board_title,
+                                        )));
+                                }
+                            }
+                        };
+
+                        let thumb_filename = format!("{}.png", hash);
+                        let thumb_path = format!("{}{}", config.thumb_dir(), thumb_filename);
+                        if !std::path::Path::new(&thumb_path).exists() {
+                            let src_for_thumb = std::path::PathBuf::from(&filepath);
+                            let dest_for_thumb = std::path::PathBuf::from(&thumb_path);
+                            if let Err(e) = web::block(move || {
+                                processor::process_image(&src_for_thumb, &dest_for_thumb)
+                            })
+                            .await?
+                            {
+                                error!("Failed to generate thumbnail for {}: {}", filepath, e);
+                            }
+                        }
+                        if std::path::Path::new(&thumb_path).exists() {
+                            thumb_url = Some(format!("/uploads/thumbs/{}", thumb_filename));
+                        }
+
+                        let details = match existing_details {
+                            Some(details) => details,
+                            None => {
+                                let (width, height) =
+                                    processor::dimensions(std::path::Path::new(&filepath))
+                                        .unwrap_or((0, 0));
+                                let details = upload_manager::Details {
+                                    path: filepath.clone(),
+                                    mime: format!("image/{}", sniffed.extension()),
+                                    width,
+                                    height,
+                                };
+                                let conn = conn_data.lock().unwrap();
+                                if let Err(e) = upload_manager::insert(&conn, &hash, &details) {
+                                    error!("Failed to record media details: {}", e);
+                                    return Ok(HttpResponse::InternalServerError()
+                                        .content_type("text/html")
+                                        .body(render_error_page(
+                                            "Internal Server Error",
+                                            "Failed to process upload",
+                                            &config.board_title,
+                                        )));
+                                }
+                                details
+                            }
+                        };
+                        if details.width > 0 && details.height > 0 {
+                            media_width = Some(details.width);
+                            media_height = Some(details.height);
+                        }
+
+                        let generated_filename = format!("{}.{}", hash, sniffed.extension());
+                        span.record("generated_filename", &generated_filename);
+                        media_url = Some(format!("/uploads/images/{}", generated_filename));
+                        media_type = Some(MediaType::Image);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if name.trim().is_empty() || subject.trim().is_empty() || body.trim().is_empty() {
+        return Ok(HttpResponse::BadRequest()
+            .content_type("text/html")
+            .body(render_error_page(
+                "Bad Request",
+                "Name, Subject, and Comment cannot be empty",
+                &config.board_title,
+            )));
+    }
+
+    let post = Post {
+        id: 0, // Will be assigned by autoincrement
+        name: name.trim().to_string(),
+        subject: subject.trim().to_string(),
+        body: body.trim().to_string(),
+        timestamp: Utc::now().timestamp(),
+        media_url,
+        media_type,
+        thumb_url,
+        media_width,
+        media_height,
+    };
+
+    {
+        let conn = conn_data.lock().unwrap();
+        if let Err(e) = insert_post(&conn, &post) {
+            error!("Failed to save post: {}", e);
+            return Ok(HttpResponse::InternalServerError()
+                .content_type("text/html")
+                .body(render_error_page(
+                    "Internal Server Error",
+                    "Failed to save post",
+                    &config.board_title,
+                )));
+        }
+    }
+
+    let elapsed_ms = start.elapsed().as_millis() as u64;
+    span.record("elapsed_ms", elapsed_ms);
+    info!(elapsed_ms, "post created");
+
+    Ok(HttpResponse::SeeOther()
+        .append_header(("Location", "/"))
+        .finish())
+}
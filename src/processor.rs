@@ -0,0 +1,61 @@
+//! Derivative generation for uploaded media (thumbnails, poster frames).
+//!
+//! Mirrors the shape of pict-rs's `processor` module: a small set of
+//! functions that take a validated source file on disk and produce a
+//! derivative alongside it, independent of how the source was uploaded.
+
+use image::imageops::FilterType;
+use image::GenericImageView;
+use std::path::Path;
+use std::process::Command;
+use tracing::instrument;
+
+/// Bounding box for generated thumbnails. Aspect ratio is preserved.
+const THUMB_MAX_DIM: u32 = 250;
+
+/// Generate a bounded thumbnail for a still or animated image already on
+/// disk at `src_path`, writing it to `dest_path`. Animated images are
+/// thumbnailed from their first frame.
+#[instrument(skip_all, fields(src = %src_path.display(), dest = %dest_path.display()))]
+pub fn process_image(src_path: &Path, dest_path: &Path) -> Result<(), image::ImageError> {
+    let img = image::open(src_path)?;
+    let thumb = img.resize(THUMB_MAX_DIM, THUMB_MAX_DIM, FilterType::Lanczos3);
+    thumb.save(dest_path)
+}
+
+/// Generate a bounded thumbnail for an MP4 video at `src_path` by
+/// extracting its first frame with `ffmpeg` and scaling that frame down,
+/// writing the result to `dest_path`.
+///
+/// Requires an `ffmpeg` binary on `PATH`; this is an external runtime
+/// dependency, not a crate. Callers must treat a returned `Err` as "no
+/// thumbnail was written" rather than surfacing it as an upload failure.
+#[instrument(skip_all, fields(src = %src_path.display(), dest = %dest_path.display()))]
+pub fn process_video(src_path: &Path, dest_path: &Path) -> std::io::Result<()> {
+    let frame_path = dest_path.with_extension("frame.png");
+
+    let status = Command::new("ffmpeg")
+        .args(["-y", "-i"])
+        .arg(src_path)
+        .args(["-frames:v", "1"])
+        .arg(&frame_path)
+        .output()?;
+
+    if !status.status.success() {
+        return Err(std::io::Error::other("ffmpeg failed to extract first frame"));
+    }
+
+    let img = image::open(&frame_path)
+        .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?;
+    let thumb = img.resize(THUMB_MAX_DIM, THUMB_MAX_DIM, FilterType::Lanczos3);
+    thumb.save(dest_path).map_err(std::io::Error::other)?;
+
+    let _ = std::fs::remove_file(&frame_path);
+    Ok(())
+}
+
+/// Dimensions of an already-decoded image, used to size the `<img>` tag
+/// without an extra probe.
+pub fn dimensions(src_path: &Path) -> Result<(u32, u32), image::ImageError> {
+    Ok(image::open(src_path)?.dimensions())
+}
@@ -0,0 +1,200 @@
+//! Byte-range media serving.
+//!
+//! `actix_files::Files` serves a whole file per request, so browsers can't
+//! seek video playback and end up re-downloading the whole file on every
+//! request. This module serves uploaded media directly, honoring `Range`
+//! requests (seeking, `206 Partial Content`) and `If-Modified-Since`
+//! (`304 Not Modified`), with a long-lived `Cache-Control` since uploads
+//! are content-immutable once written.
+
+use crate::config::Config;
+use crate::upload_manager;
+use actix_web::http::header;
+use actix_web::{web, HttpRequest, HttpResponse};
+use rusqlite::Connection;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+const CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+
+/// Content-addressed filenames are always a 64-char hex SHA-256 hash plus
+/// one of the extensions `validate::SniffedFormat` produces. Anything else
+/// (in particular `..`, `/`, or a bare `\`) is rejected before it ever
+/// reaches a filesystem join, so a crafted `filename` path segment can't
+/// escape `dir`.
+fn is_safe_filename(filename: &str) -> bool {
+    let Some((stem, ext)) = filename.rsplit_once('.') else {
+        return false;
+    };
+    stem.len() == 64
+        && stem.bytes().all(|b| b.is_ascii_hexdigit())
+        && matches!(ext, "jpg" | "png" | "webp" | "gif" | "mp4")
+}
+
+/// Serve `/uploads/images/{filename}` with range and cache support.
+pub async fn serve_image(
+    req: HttpRequest,
+    filename: web::Path<String>,
+    config: web::Data<Config>,
+    conn_data: web::Data<Arc<Mutex<Connection>>>,
+) -> actix_web::Result<HttpResponse> {
+    serve_file(&config.image_dir(), &filename, &req, &conn_data).await
+}
+
+/// Serve `/uploads/videos/{filename}` with range and cache support.
+pub async fn serve_video(
+    req: HttpRequest,
+    filename: web::Path<String>,
+    config: web::Data<Config>,
+    conn_data: web::Data<Arc<Mutex<Connection>>>,
+) -> actix_web::Result<HttpResponse> {
+    serve_file(&config.video_dir(), &filename, &req, &conn_data).await
+}
+
+async fn serve_file(
+    dir: &str,
+    filename: &str,
+    req: &HttpRequest,
+    conn_data: &web::Data<Arc<Mutex<Connection>>>,
+) -> actix_web::Result<HttpResponse> {
+    if !is_safe_filename(filename) {
+        return Ok(HttpResponse::NotFound().finish());
+    }
+
+    let path = Path::new(dir).join(filename);
+    let metadata = match std::fs::metadata(&path) {
+        Ok(m) if m.is_file() => m,
+        _ => return Ok(HttpResponse::NotFound().finish()),
+    };
+
+    let file_len = metadata.len();
+    let last_modified = metadata.modified()?;
+    let last_modified_str = httpdate::fmt_http_date(last_modified);
+
+    if let Some(since) = req
+        .headers()
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| httpdate::parse_http_date(v).ok())
+    {
+        let last_modified_secs = last_modified
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| std::time::UNIX_EPOCH + std::time::Duration::from_secs(d.as_secs()))
+            .unwrap_or(last_modified);
+
+        if last_modified_secs <= since {
+            return Ok(HttpResponse::NotModified()
+                .insert_header((header::CACHE_CONTROL, CACHE_CONTROL))
+                .finish());
+        }
+    }
+
+    // Prefer the mime cached in the `media` table at upload time (sniffed from
+    // content, not guessed from the extension); fall back for files predating
+    // that table or any lookup failure.
+    let hash = Path::new(filename)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or(filename);
+    let cached_mime = {
+        let conn = conn_data.lock().unwrap();
+        upload_manager::lookup(&conn, hash)
+            .ok()
+            .flatten()
+            .map(|d| d.mime)
+    };
+    let content_type = match &cached_mime {
+        Some(mime) => mime.clone(),
+        None => mime_guess::from_path(&path)
+            .first_or_octet_stream()
+            .to_string(),
+    };
+    let mut file = std::fs::File::open(&path)?;
+
+    let range = req
+        .headers()
+        .get(header::RANGE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| parse_range(v, file_len));
+
+    match range {
+        Some(RangeResult::NotSatisfiable) => Ok(HttpResponse::RangeNotSatisfiable()
+            .insert_header((header::CONTENT_RANGE, format!("bytes */{}", file_len)))
+            .finish()),
+        Some(RangeResult::Satisfiable(start, end)) => {
+            let len = (end - start + 1) as usize;
+            file.seek(SeekFrom::Start(start))?;
+            let mut buf = vec![0u8; len];
+            file.read_exact(&mut buf)?;
+
+            Ok(HttpResponse::PartialContent()
+                .content_type(content_type.as_str())
+                .insert_header((header::ACCEPT_RANGES, "bytes"))
+                .insert_header((
+                    header::CONTENT_RANGE,
+                    format!("bytes {}-{}/{}", start, end, file_len),
+                ))
+                .insert_header((header::CACHE_CONTROL, CACHE_CONTROL))
+                .insert_header((header::LAST_MODIFIED, last_modified_str))
+                .body(buf))
+        }
+        None => {
+            let mut buf = Vec::with_capacity(file_len as usize);
+            file.read_to_end(&mut buf)?;
+
+            Ok(HttpResponse::Ok()
+                .content_type(content_type.as_str())
+                .insert_header((header::ACCEPT_RANGES, "bytes"))
+                .insert_header((header::CACHE_CONTROL, CACHE_CONTROL))
+                .insert_header((header::LAST_MODIFIED, last_modified_str))
+                .body(buf))
+        }
+    }
+}
+
+/// Outcome of parsing a `Range` header against a known file length.
+enum RangeResult {
+    /// A satisfiable inclusive `(start, end)` byte offset pair.
+    Satisfiable(u64, u64),
+    /// The requested range lies entirely outside the file; the caller
+    /// should respond `416 Range Not Satisfiable` rather than fall back
+    /// to a full-file response.
+    NotSatisfiable,
+}
+
+/// Parse a single-range `Range: bytes=start-end` header. Multi-range
+/// requests and malformed headers return `None`, which falls back to the
+/// whole-file response; an in-bounds range returns `Satisfiable`, and one
+/// past the end of the file returns `NotSatisfiable`.
+fn parse_range(header_value: &str, file_len: u64) -> Option<RangeResult> {
+    let spec = header_value.strip_prefix("bytes=")?;
+    if spec.contains(',') || file_len == 0 {
+        return None;
+    }
+    let (start_s, end_s) = spec.split_once('-')?;
+
+    if start_s.is_empty() {
+        let suffix_len: u64 = end_s.parse().ok()?;
+        if suffix_len == 0 {
+            return Some(RangeResult::NotSatisfiable);
+        }
+        let start = file_len.saturating_sub(suffix_len);
+        Some(RangeResult::Satisfiable(start, file_len - 1))
+    } else {
+        let start: u64 = start_s.parse().ok()?;
+        if start >= file_len {
+            return Some(RangeResult::NotSatisfiable);
+        }
+        let end = if end_s.is_empty() {
+            file_len - 1
+        } else {
+            end_s.parse().ok()?
+        };
+        if start > end {
+            None
+        } else {
+            Some(RangeResult::Satisfiable(start, end.min(file_len - 1)))
+        }
+    }
+}
@@ -0,0 +1,81 @@
+//! Content-addressed storage for uploaded media.
+//!
+//! Each accepted upload is stored under a filename derived from the
+//! SHA-256 hash of its validated bytes rather than a random id. Reposts
+//! of the same bytes across threads reuse the existing file instead of
+//! being written again, and the hash doubles as the dedup key in the
+//! `media` table alongside cached `Details` (dimensions, content-type)
+//! for the thumbnail and serving paths to use without re-probing.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use sha2::{Digest, Sha256};
+use tracing::instrument;
+
+/// Metadata cached for a stored blob, keyed by its content hash.
+#[derive(Debug, Clone)]
+pub struct Details {
+    pub path: String,
+    pub mime: String,
+    pub width: u32,
+    pub height: u32,
+}
+
+pub fn db_init(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS media (
+            hash TEXT PRIMARY KEY,
+            path TEXT NOT NULL,
+            mime TEXT NOT NULL,
+            width INTEGER NOT NULL,
+            height INTEGER NOT NULL
+        )",
+        [],
+    )?;
+    Ok(())
+}
+
+/// SHA-256 hash of `bytes`, hex-encoded, used as the content address.
+pub fn hash_bytes(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+/// Look up cached details for a previously stored blob by hash.
+pub fn lookup(conn: &Connection, hash: &str) -> rusqlite::Result<Option<Details>> {
+    conn.query_row(
+        "SELECT path, mime, width, height FROM media WHERE hash = ?1",
+        params![hash],
+        |row| {
+            Ok(Details {
+                path: row.get(0)?,
+                mime: row.get(1)?,
+                width: row.get(2)?,
+                height: row.get(3)?,
+            })
+        },
+    )
+    .optional()
+}
+
+/// Record details for a newly stored blob.
+pub fn insert(conn: &Connection, hash: &str, details: &Details) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT OR IGNORE INTO media (hash, path, mime, width, height) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![hash, details.path, details.mime, details.width, details.height],
+    )?;
+    Ok(())
+}
+
+/// Store `bytes` under a content-addressed filename in `dir`, skipping
+/// the write if a file with that hash already exists on disk. Returns the
+/// hash and the path written (or reused).
+#[instrument(skip(bytes), fields(len = bytes.len(), extension))]
+pub fn store(dir: &str, bytes: &[u8], extension: &str) -> std::io::Result<(String, String)> {
+    let hash = hash_bytes(bytes);
+    let path = format!("{}{}.{}", dir, hash, extension);
+
+    if !std::path::Path::new(&path).exists() {
+        std::fs::write(&path, bytes)?;
+    }
+
+    Ok((hash, path))
+}